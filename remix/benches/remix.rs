@@ -1,10 +1,14 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use rand::{rngs::StdRng, CryptoRng, Rng, SeedableRng};
 use rust_elgamal::{Ciphertext, DecryptionKey, EncryptionKey, Scalar, GENERATOR_TABLE};
 
 const N_SIZE: usize = 25600;
 
-fn setup_bench() -> (Vec<Ciphertext>, Vec<Ciphertext>, (impl Rng + CryptoRng)) {
+fn setup_bench() -> (Vec<Ciphertext>, Vec<Ciphertext>, impl Rng + CryptoRng) {
+    setup_bench_sized(N_SIZE)
+}
+
+fn setup_bench_sized(size: usize) -> (Vec<Ciphertext>, Vec<Ciphertext>, impl Rng + CryptoRng) {
     let mut rng = StdRng::seed_from_u64(7);
     let dec_key = DecryptionKey::new(&mut rng);
     let enc_key = dec_key.encryption_key();
@@ -13,8 +17,8 @@ fn setup_bench() -> (Vec<Ciphertext>, Vec<Ciphertext>, (impl Rng + CryptoRng)) {
         let m = &Scalar::from((i % 2) as u32) * &GENERATOR_TABLE;
         enc_key.encrypt(m, &mut rng)
     };
-    let ct1: Vec<_> = (0..N_SIZE).map(&mut encrypt).collect();
-    let ct2: Vec<_> = (0..N_SIZE).map(&mut encrypt).collect();
+    let ct1: Vec<_> = (0..size).map(&mut encrypt).collect();
+    let ct2: Vec<_> = (0..size).map(&mut encrypt).collect();
 
     (ct1, ct2, rng)
 }
@@ -84,11 +88,34 @@ fn bench_all(c: &mut Criterion) {
     });
 }
 
+fn bench_crossover(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Crossover");
+    group.sample_size(10);
+
+    for size in [10, 100, 1000, N_SIZE] {
+        let (mut ct1, mut ct2, mut rng) = setup_bench_sized(size);
+        let enc_key = EncryptionKey::from(&Scalar::random(&mut rng) * &GENERATOR_TABLE);
+
+        group.bench_with_input(BenchmarkId::new("base", size), &size, |b, _| {
+            b.iter(|| {
+                remix::remix(&mut ct1, &mut ct2, &enc_key);
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("parallel", size), &size, |b, _| {
+            b.iter(|| {
+                remix::par::remix(&mut ct1, &mut ct2, &enc_key);
+            })
+        });
+    }
+}
+
 criterion_group!(
     benches,
     bench_shuffle_pairs,
     bench_shuffle_bits,
     bench_rerandomise,
     bench_all,
+    bench_crossover,
 );
 criterion_main!(benches);
@@ -0,0 +1,70 @@
+//! Hash commitments to re-mixed ciphertexts, so the output of a re-mixing can be published before the decryption
+//! phase and any later tampering detected.
+
+use rust_elgamal::{Ciphertext, EncryptionKey};
+use sha2::{Digest, Sha256};
+
+/// SHA-256 digest of the compressed [`Ciphertext`]s of both slices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Commitment(pub [u8; 32]);
+
+impl Commitment {
+    pub fn new(x_cipher: &[Ciphertext], y_cipher: &[Ciphertext]) -> Self {
+        let mut hasher = Sha256::new();
+        for cipher in [x_cipher, y_cipher] {
+            hasher.update((cipher.len() as u64).to_le_bytes());
+            for ct in cipher {
+                let (c1, c2) = ct.inner();
+                hasher.update(c1.compress().as_bytes());
+                hasher.update(c2.compress().as_bytes());
+            }
+        }
+        Self(hasher.finalize().into())
+    }
+}
+
+/// Same as [remix](fn@super::remix) but also returns a [`Commitment`] to the output.
+pub fn remix_committed(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+) -> Commitment {
+    super::remix(x_cipher, y_cipher, enc_key);
+    Commitment::new(x_cipher, y_cipher)
+}
+
+/// Checks whether the [`Ciphertext`]s are the ones the `commitment` was made to.
+pub fn verify_commitment(
+    x_cipher: &[Ciphertext],
+    y_cipher: &[Ciphertext],
+    commitment: &Commitment,
+) -> bool {
+    Commitment::new(x_cipher, y_cipher) == *commitment
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{CryptoRng, Rng};
+    use rstest::rstest;
+    use rust_elgamal::DecryptionKey;
+
+    use super::*;
+    use crate::testing::fixtures::*;
+
+    #[rstest]
+    fn test_remix_committed(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let enc_key = dec_key.encryption_key();
+
+        let commitment = remix_committed(&mut ct1, &mut ct2, enc_key);
+        assert!(verify_commitment(&ct1, &ct2, &commitment));
+        assert!(!verify_commitment(&ct1, &ct2[1..], &commitment));
+
+        ct2[5] = enc_key.rerandomise(ct2[5], &mut rng);
+        assert!(!verify_commitment(&ct1, &ct2, &commitment));
+    }
+}
@@ -0,0 +1,87 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rust_elgamal::{Ciphertext, EncryptionKey};
+
+use crate::{pipeline::Pipeline, RemixRng};
+
+/// Holds the source of randomness of a re-mixing so a single [`RemixRng`] drives every procedure. Seeding it makes the
+/// whole re-mixing reproducible.
+#[derive(Debug, Clone)]
+pub struct RemixContext<R> {
+    rng: R,
+}
+
+impl<R: RemixRng> RemixContext<R> {
+    pub fn new(rng: R) -> Self {
+        Self { rng }
+    }
+
+    /// See [`shuffle_pairs`](fn@super::shuffle_pairs).
+    pub fn shuffle_pairs(&mut self, x_cipher: &mut [Ciphertext], y_cipher: &mut [Ciphertext]) {
+        super::shuffle_pairs(x_cipher, y_cipher, &mut self.rng);
+    }
+
+    /// See [`shuffle_bits`](fn@super::shuffle_bits).
+    pub fn shuffle_bits(&mut self, x_cipher: &mut [Ciphertext], y_cipher: &mut [Ciphertext]) {
+        super::shuffle_bits(x_cipher, y_cipher, &mut self.rng);
+    }
+
+    /// See [`rerandomise`](fn@super::rerandomise).
+    pub fn rerandomise(
+        &mut self,
+        x_cipher: &mut [Ciphertext],
+        y_cipher: &mut [Ciphertext],
+        enc_key: &EncryptionKey,
+    ) {
+        super::rerandomise(x_cipher, y_cipher, enc_key, &mut self.rng);
+    }
+
+    /// See [`remix`](fn@super::remix).
+    pub fn remix(
+        &mut self,
+        x_cipher: &mut [Ciphertext],
+        y_cipher: &mut [Ciphertext],
+        enc_key: &EncryptionKey,
+    ) {
+        super::assert_even_len(x_cipher, y_cipher);
+        Pipeline::remix().run_with_rng(x_cipher, y_cipher, enc_key, &mut self.rng);
+    }
+
+    pub fn rng_mut(&mut self) -> &mut R {
+        &mut self.rng
+    }
+
+    pub fn into_rng(self) -> R {
+        self.rng
+    }
+}
+
+impl RemixContext<StdRng> {
+    /// Creates a context whose randomness is fully determined by `seed`, for reproducible re-mixing in tests.
+    pub fn from_seed(seed: u64) -> Self {
+        Self::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use rust_elgamal::DecryptionKey;
+
+    use super::*;
+    use crate::testing::fixtures::*;
+
+    #[rstest]
+    fn test_seeded_remix_is_reproducible(ct1: Vec<Ciphertext>, dec_key: DecryptionKey) {
+        let enc_key = dec_key.encryption_key();
+        let remix_seeded = |seed| {
+            let (mut x, mut y) = (ct1.clone(), ct1.clone());
+            RemixContext::from_seed(seed).remix(&mut x, &mut y, enc_key);
+            (x, y)
+        };
+
+        assert_eq!(remix_seeded(42), remix_seeded(42));
+        assert_ne!(remix_seeded(42), remix_seeded(43));
+        let (x, _) = remix_seeded(42);
+        crate::testing::assert_multiset_preserved(&dec_key, &ct1, &x);
+    }
+}
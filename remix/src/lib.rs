@@ -1,44 +1,229 @@
 //! Implementation of the re-mixing described in the article :TBD:.
 
-use rand::{CryptoRng, Rng};
-use rust_elgamal::{Ciphertext, EncryptionKey, Scalar};
-use std::iter::zip;
+use rand::{CryptoRng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rust_elgamal::{Ciphertext, EncryptionKey, Identity, IsIdentity, RistrettoPoint, Scalar};
+use std::{fmt, iter::zip};
 
+pub mod commitment;
+pub mod context;
 pub mod par;
+pub mod pipeline;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Source of randomness accepted by the re-mixing functions. It's implemented for every cryptographically secure
+/// [`Rng`], so a hardware or HSM-backed generator can be plugged in by implementing [`rand::RngCore`] and
+/// [`CryptoRng`] for it.
+pub trait RemixRng: Rng + CryptoRng {}
+
+impl<R: Rng + CryptoRng + ?Sized> RemixRng for R {}
 
 /// Shuffles groups of 2 [`Ciphertext`]s randomly but equally for both slices.
 /// So, the ciphertext of the slices at given index before shuffling will endup randomly but at the same index after
 /// the shuffle.
 /// If the length of the slice it's not divisible by 2, meaning there's an incomplete pair, that lonely ciphertext is
-/// not shuffled.
+/// not shuffled, see [`shuffle_pairs_with_policy`] to handle it otherwise.
 /// Internally, it uses the [Fisher-Yates shuffle].
 ///
 /// [Fisher-Yates shuffle]: https://en.wikipedia.org/wiki/Fisher%E2%80%93Yates_shuffle
 pub fn shuffle_pairs(
     x_cipher: &mut [Ciphertext],
     y_cipher: &mut [Ciphertext],
-    rng: &mut (impl Rng + CryptoRng),
+    rng: &mut impl RemixRng,
 ) {
     // TODO: Method only accepts Ciphertext slices but it can be generic over any type
-    const STEP: usize = 2;
-    let total_pairs = x_cipher.len() / STEP;
-    for (pair_idx, arr_idx) in (0..x_cipher.len() - STEP).step_by(STEP).enumerate() {
-        let swap_idx = rng.gen_range(pair_idx..total_pairs) * STEP;
+    let total_pairs = x_cipher.len() / 2;
+    for pair_idx in 0..total_pairs.saturating_sub(1) {
+        let swap_idx = rng.gen_range(pair_idx..total_pairs);
+        swap_pairs(x_cipher, y_cipher, pair_idx, swap_idx);
+    }
+}
+
+/// What [`shuffle_pairs_with_policy`] does with the lonely [`Ciphertext`] of an odd-length slice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LonelyPolicy {
+    /// Leave it untouched, like [`shuffle_pairs`].
+    #[default]
+    Ignore,
+    /// Refuse to shuffle.
+    Error,
+    /// Leave it in place but rerandomise it.
+    RerandomiseOnly,
+}
+
+/// Error returned when the slices have an odd length and the [`LonelyPolicy`] doesn't allow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OddLengthError(pub usize);
+
+impl fmt::Display for OddLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ciphertext slices have odd length {}", self.0)
+    }
+}
+
+impl std::error::Error for OddLengthError {}
+
+/// Same as [`shuffle_pairs`] but handles the lonely [`Ciphertext`] of odd-length slices according to `policy`.
+///
+/// # Errors
+/// If the slices have an odd length and the policy is [`LonelyPolicy::Error`], in which case nothing is shuffled.
+///
+/// # Panics
+/// If the policy is [`LonelyPolicy::RerandomiseOnly`] and the slices have different lengths.
+pub fn shuffle_pairs_with_policy(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+    policy: LonelyPolicy,
+    rng: &mut impl RemixRng,
+) -> Result<(), OddLengthError> {
+    let len = x_cipher.len();
+    if len.is_multiple_of(2) {
+        shuffle_pairs(x_cipher, y_cipher, rng);
+        return Ok(());
+    }
+    match policy {
+        LonelyPolicy::Ignore => shuffle_pairs(x_cipher, y_cipher, rng),
+        LonelyPolicy::Error => return Err(OddLengthError(len)),
+        LonelyPolicy::RerandomiseOnly => {
+            shuffle_pairs(x_cipher, y_cipher, rng);
+            rerandomise_selected(x_cipher, y_cipher, enc_key, &[len - 1], rng);
+        }
+    }
+    Ok(())
+}
+
+/// Checkpoint of a [`shuffle_pairs_resumable`]: the seed the shuffle draws its randomness from and the number of
+/// Fisher-Yates steps already performed. Persisting it is enough to resume an interrupted shuffle, even after a crash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShuffleState {
+    pub seed: [u8; 32],
+    pub step: usize,
+}
+
+impl ShuffleState {
+    /// Starts a new shuffle with a seed drawn from `rng`.
+    pub fn new(rng: &mut impl RemixRng) -> Self {
+        Self {
+            seed: rng.gen(),
+            step: 0,
+        }
+    }
+}
+
+/// Same as [`shuffle_pairs`] but performs at most `max_steps` steps of the shuffle starting from `state`, and updates
+/// it with the progress made. Returns `true` once the shuffle is complete.
+/// The randomness of every step is derived from `(state.seed, step)` only, it uses the ChaCha20 stream numbered
+/// `step`, so resuming from a persisted `state` gives the same result as an uninterrupted shuffle.
+///
+/// # Panics
+/// If `state.step` is past the last step of the shuffle for these slices, e.g. a stale state or a shorter slice.
+pub fn shuffle_pairs_resumable(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    state: &mut ShuffleState,
+    max_steps: usize,
+) -> bool {
+    let total_pairs = x_cipher.len() / 2;
+    let total_steps = total_pairs.saturating_sub(1);
+    assert!(
+        state.step <= total_steps,
+        "shuffle state step {} is past the last step {total_steps}",
+        state.step
+    );
+    let end = state.step.saturating_add(max_steps).min(total_steps);
+    for pair_idx in state.step..end {
+        let mut step_rng = ChaCha20Rng::from_seed(state.seed);
+        step_rng.set_stream(pair_idx as u64);
+        let swap_idx = step_rng.gen_range(pair_idx..total_pairs);
+        swap_pairs(x_cipher, y_cipher, pair_idx, swap_idx);
+    }
+    state.step = state.step.max(end);
+    state.step == total_steps
+}
+
+/// Same as [`shuffle_pairs`] but the pairs starting at the `anchors` indices stay in place, only the other pairs are
+/// shuffled among themselves.
+///
+/// # Panics
+/// If an anchor is odd, meaning it's not the start of a pair, or it's not the start of a complete pair.
+pub fn shuffle_pairs_with_anchors(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    anchors: &[usize],
+    rng: &mut impl RemixRng,
+) {
+    let total_pairs = x_cipher.len() / 2;
+    for &anchor in anchors {
+        assert!(
+            anchor.is_multiple_of(2),
+            "anchor {anchor} is not pair-aligned"
+        );
+        assert!(
+            anchor / 2 < total_pairs,
+            "anchor {anchor} out of range for length {}",
+            x_cipher.len()
+        );
+    }
+    let free: Vec<_> = (0..total_pairs)
+        .filter(|pair_idx| !anchors.contains(&(pair_idx * 2)))
+        .collect();
+    for i in 0..free.len().saturating_sub(1) {
+        let j = rng.gen_range(i..free.len());
+        swap_pairs(x_cipher, y_cipher, free[i], free[j]);
+    }
+}
 
-        // TODO: make it more generic over STEP, this only works for pairs (STEP=2)
-        x_cipher.swap(arr_idx, swap_idx);
-        x_cipher.swap(arr_idx + 1, swap_idx + 1);
-        y_cipher.swap(arr_idx, swap_idx);
-        y_cipher.swap(arr_idx + 1, swap_idx + 1);
+/// Draws the random tape that drives [`shuffle_pairs`] for `len` ciphertexts, so it can be recorded and replayed with
+/// [`shuffle_pairs_with_tape`]. Replaying it gives the same result as [`shuffle_pairs`] with the same `rng`.
+pub fn shuffle_tape(len: usize, rng: &mut impl RemixRng) -> Vec<usize> {
+    let total_pairs = len / 2;
+    (0..total_pairs.saturating_sub(1))
+        .map(|pair_idx| rng.gen_range(pair_idx..total_pairs))
+        .collect()
+}
+
+/// Same as [`shuffle_pairs`] but takes the swap targets from a precomputed random `tape`, where `tape[i]` is the pair
+/// swapped with pair `i`. Anyone holding the tape can reproduce the shuffle exactly.
+///
+/// # Panics
+/// If the tape doesn't have one entry per step of the shuffle or an entry is outside `i..total_pairs`.
+pub fn shuffle_pairs_with_tape(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    tape: &[usize],
+) {
+    let total_pairs = x_cipher.len() / 2;
+    assert_eq!(
+        tape.len(),
+        total_pairs.saturating_sub(1),
+        "tape must have one entry per shuffle step"
+    );
+    for (pair_idx, &swap_idx) in tape.iter().enumerate() {
+        assert!(
+            (pair_idx..total_pairs).contains(&swap_idx),
+            "tape entry {swap_idx} at step {pair_idx} is out of range"
+        );
+        swap_pairs(x_cipher, y_cipher, pair_idx, swap_idx);
     }
 }
 
+fn swap_pairs(x_cipher: &mut [Ciphertext], y_cipher: &mut [Ciphertext], a: usize, b: usize) {
+    // TODO: make it more generic over the step, this only works for pairs
+    let (a, b) = (a * 2, b * 2);
+    x_cipher.swap(a, b);
+    x_cipher.swap(a + 1, b + 1);
+    y_cipher.swap(a, b);
+    y_cipher.swap(a + 1, b + 1);
+}
+
 /// Iterates over every pair of [`Ciphertext`] and flips a coin (probability of 50%) to swap the ciphertexts
 /// on the pair.
 pub fn shuffle_bits(
     x_cipher: &mut [Ciphertext],
     y_cipher: &mut [Ciphertext],
-    rng: &mut (impl Rng + CryptoRng),
+    rng: &mut impl RemixRng,
 ) {
     // TODO: Method only accepts Ciphertext slices but it can be generic over any type
     for i in (0..x_cipher.len()).step_by(2) {
@@ -50,13 +235,41 @@ pub fn shuffle_bits(
     }
 }
 
+/// Research variant of [`shuffle_bits`] where the coin lands on swap with probability `p`, to study how a biased coin
+/// degrades the mixing. Only `p = 0.5`, which is what [`shuffle_bits`] does, hides the bits, so use that one for
+/// anything but analysis.
+///
+/// # Panics
+/// If `p` is not within `0.0..=1.0`.
+pub fn shuffle_bits_biased(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    p: f64,
+    rng: &mut impl RemixRng,
+) {
+    assert!(
+        (0.0..=1.0).contains(&p),
+        "probability {p} is not within 0..=1"
+    );
+    for i in (0..x_cipher.len()).step_by(2) {
+        if rng.gen_bool(p) {
+            x_cipher.swap(i, i + 1);
+            y_cipher.swap(i, i + 1);
+        }
+    }
+}
+
 /// Iterates over every [`Ciphertext`] and rerandomises with the same but random [`Scalar`].
+///
+/// # Panics
+/// If the slices have different lengths.
 pub fn rerandomise(
     x_cipher: &mut [Ciphertext],
     y_cipher: &mut [Ciphertext],
     enc_key: &EncryptionKey,
-    rng: &mut (impl Rng + CryptoRng),
+    rng: &mut impl RemixRng,
 ) {
+    assert_same_len(x_cipher, y_cipher);
     zip(x_cipher, y_cipher).for_each(|(x, y)| {
         let r = Scalar::from(rng.gen::<u32>());
         *x = enc_key.rerandomise_with(*x, r);
@@ -64,65 +277,181 @@ pub fn rerandomise(
     });
 }
 
+/// Same as [`rerandomise`] but leaves the inputs untouched and returns the rerandomised [`Ciphertext`]s.
+pub fn rerandomise_to(
+    x_cipher: &[Ciphertext],
+    y_cipher: &[Ciphertext],
+    enc_key: &EncryptionKey,
+    rng: &mut impl RemixRng,
+) -> (Vec<Ciphertext>, Vec<Ciphertext>) {
+    let mut x_out = x_cipher.to_vec();
+    let mut y_out = y_cipher.to_vec();
+    rerandomise(&mut x_out, &mut y_out, enc_key, rng);
+    (x_out, y_out)
+}
+
+/// Research variant of [`rerandomise`] that adds `n` independent encryptions of zero to every [`Ciphertext`].
+/// The result is still a valid rerandomisation, with `n = 1` being the standard [`rerandomise`] and `n = 0` leaving
+/// the ciphertexts untouched.
+pub fn rerandomise_n(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+    n: usize,
+    rng: &mut impl RemixRng,
+) {
+    for _ in 0..n {
+        rerandomise(x_cipher, y_cipher, enc_key, rng);
+    }
+}
+
+/// Same as [`rerandomise`] but only rerandomises the [`Ciphertext`]s at the given `indices`, leaving the others
+/// untouched.
+///
+/// # Panics
+/// If the slices have different lengths or any index is out of range.
+pub fn rerandomise_selected(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+    indices: &[usize],
+    rng: &mut impl RemixRng,
+) {
+    assert_same_len(x_cipher, y_cipher);
+    let len = x_cipher.len();
+    for &i in indices {
+        assert!(i < len, "index {i} out of range for length {len}");
+        let r = Scalar::from(rng.gen::<u32>());
+        x_cipher[i] = enc_key.rerandomise_with(x_cipher[i], r);
+        y_cipher[i] = enc_key.rerandomise_with(y_cipher[i], r);
+    }
+}
+
+/// Pads both [`Ciphertext`] vectors to an even length, which [`remix`] requires, by appending a fresh encryption of
+/// the identity point (a `0` bit) to each.
+/// Returns whether padding happened so the caller can account for the extra element, since it is not part of the
+/// original code.
+///
+/// # Panics
+/// If the vectors have different lengths.
+pub fn pad_odd(
+    x_cipher: &mut Vec<Ciphertext>,
+    y_cipher: &mut Vec<Ciphertext>,
+    enc_key: &EncryptionKey,
+    rng: &mut impl RemixRng,
+) -> bool {
+    assert_same_len(x_cipher, y_cipher);
+    if x_cipher.len().is_multiple_of(2) {
+        return false;
+    }
+    x_cipher.push(enc_key.encrypt(RistrettoPoint::identity(), rng));
+    y_cipher.push(enc_key.encrypt(RistrettoPoint::identity(), rng));
+    true
+}
+
+/// Checks whether the [`Ciphertext`]s are degenerate inputs that mixing cannot hide: either all of them are the
+/// same ciphertext or none of them carries randomness: every ciphertext's first component `c1` is the identity, i.e.
+/// it was encrypted with a zero nonce.
+/// An empty slice is not degenerate.
+pub fn is_degenerate(cts: &[Ciphertext]) -> bool {
+    let all_equal = cts.len() > 1 && cts.windows(2).all(|w| w[0] == w[1]);
+    let no_randomness = !cts.is_empty() && cts.iter().all(|ct| ct.inner().0.is_identity());
+    all_equal || no_randomness
+}
+
 /// Encapsulates all the procedures of re-mixing into one function.
-/// It calls [`shuffle_pairs`], [`shuffle_bits`], [`rerandomise`] in this order.
+/// It calls [`shuffle_pairs`], [`shuffle_bits`], [`rerandomise`] in this order, see [`pipeline::Pipeline`] for
+/// other combinations and [`context::RemixContext`] to control the randomness.
+///
+/// # Panics
+/// If the slices have an odd length, use [`pad_odd`] first for those.
 pub fn remix(x_cipher: &mut [Ciphertext], y_cipher: &mut [Ciphertext], enc_key: &EncryptionKey) {
-    let mut rng = rand::thread_rng();
-    shuffle_pairs(x_cipher, y_cipher, &mut rng);
-    shuffle_bits(x_cipher, y_cipher, &mut rng);
-    rerandomise(x_cipher, y_cipher, enc_key, &mut rng);
+    context::RemixContext::new(rand::thread_rng()).remix(x_cipher, y_cipher, enc_key);
+}
+
+fn assert_same_len(x_cipher: &[Ciphertext], y_cipher: &[Ciphertext]) {
+    assert_eq!(
+        x_cipher.len(),
+        y_cipher.len(),
+        "ciphertext slices must have the same length"
+    );
+}
+
+fn assert_even_len(x_cipher: &[Ciphertext], y_cipher: &[Ciphertext]) {
+    assert!(
+        x_cipher.len().is_multiple_of(2) && y_cipher.len().is_multiple_of(2),
+        "ciphertext slices must have even length, pad them with pad_odd"
+    );
 }
 
 #[cfg(test)]
 mod tests {
-    use rand::{rngs::StdRng, SeedableRng};
-    use rstest::{fixture, rstest};
+    use rstest::rstest;
     use rust_elgamal::{DecryptionKey, Scalar, GENERATOR_TABLE};
     use std::slice;
 
     use super::*;
+    use crate::testing::fixtures::*;
 
-    const N_SIZE: usize = 32;
-
-    #[fixture]
-    fn rng() -> impl Rng + CryptoRng {
-        StdRng::seed_from_u64(7)
-    }
-
-    #[fixture]
-    fn dec_key() -> DecryptionKey {
-        let mut rng = rng();
-        DecryptionKey::new(&mut rng)
-    }
-
-    #[fixture]
-    fn ct1() -> Vec<Ciphertext> {
-        let mut rng = rng();
-        let dec_key = dec_key();
-        let enc_key = dec_key.encryption_key();
+    #[rstest]
+    fn test_shuffle_pairs(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let prev_ct = ct1.clone();
 
-        (0..N_SIZE)
-            .map(|i| enc_key.encrypt(&Scalar::from((i % 2) as u8) * &GENERATOR_TABLE, &mut rng))
-            .collect()
-    }
+        shuffle_pairs(&mut ct1, &mut ct2, &mut rng);
 
-    #[fixture]
-    fn ct2() -> Vec<Ciphertext> {
-        ct1() // a clone for now
+        assert_eq!(ct1, ct2);
+        assert_ne!(prev_ct, ct1);
     }
 
     #[rstest]
-    fn test_shuffle_pairs(
+    #[case(LonelyPolicy::Ignore)]
+    #[case(LonelyPolicy::Error)]
+    #[case(LonelyPolicy::RerandomiseOnly)]
+    fn test_shuffle_pairs_with_policy(
+        #[case] policy: LonelyPolicy,
         mut ct1: Vec<Ciphertext>,
         mut ct2: Vec<Ciphertext>,
         mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
     ) {
+        ct1.push(ct1[0]);
+        ct2.push(ct2[0]);
+        let lonely = N_SIZE;
         let prev_ct = ct1.clone();
 
-        shuffle_pairs(&mut ct1, &mut ct2, &mut rng);
+        let res = shuffle_pairs_with_policy(
+            &mut ct1,
+            &mut ct2,
+            dec_key.encryption_key(),
+            policy,
+            &mut rng,
+        );
 
         assert_eq!(ct1, ct2);
-        assert_ne!(prev_ct, ct1);
+        assert_eq!(
+            dec_key.decrypt(prev_ct[lonely]),
+            dec_key.decrypt(ct1[lonely])
+        );
+        match policy {
+            LonelyPolicy::Ignore => {
+                assert_eq!(res, Ok(()));
+                assert_ne!(prev_ct, ct1);
+                assert_eq!(prev_ct[lonely], ct1[lonely]);
+            }
+            LonelyPolicy::Error => {
+                assert_eq!(res, Err(OddLengthError(N_SIZE + 1)));
+                assert_eq!(prev_ct, ct1);
+            }
+            LonelyPolicy::RerandomiseOnly => {
+                assert_eq!(res, Ok(()));
+                assert_ne!(prev_ct[..lonely], ct1[..lonely]);
+                assert_ne!(prev_ct[lonely], ct1[lonely]);
+            }
+        }
     }
 
     #[rstest]
@@ -139,6 +468,44 @@ mod tests {
         assert_ne!(prev_c, ct1);
     }
 
+    #[rstest]
+    fn test_shuffle_bits_biased(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let prev_ct = ct1.clone();
+        shuffle_bits_biased(&mut ct1, &mut ct2, 0.0, &mut rng);
+        assert_eq!(prev_ct, ct1);
+
+        shuffle_bits_biased(&mut ct1, &mut ct2, 1.0, &mut rng);
+        assert_eq!(ct1, ct2);
+        for pair in 0..N_SIZE / 2 {
+            assert_eq!(prev_ct[2 * pair], ct1[2 * pair + 1]);
+            assert_eq!(prev_ct[2 * pair + 1], ct1[2 * pair]);
+        }
+
+        const ROUNDS: usize = 1000;
+        let mut swaps = 0;
+        for _ in 0..ROUNDS {
+            let prev_ct = ct1.clone();
+            shuffle_bits_biased(&mut ct1, &mut ct2, 0.25, &mut rng);
+            swaps += zip(&prev_ct, &ct1).filter(|(prev, ct)| prev != ct).count() / 2;
+        }
+        let ratio = swaps as f64 / (ROUNDS * N_SIZE / 2) as f64;
+        assert!((0.22..0.28).contains(&ratio), "swap ratio {ratio}");
+    }
+
+    #[rstest]
+    #[should_panic(expected = "not within")]
+    fn test_shuffle_bits_biased_invalid_probability(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        shuffle_bits_biased(&mut ct1, &mut ct2, 1.5, &mut rng);
+    }
+
     #[rstest]
     fn test_rerandomise(mut rng: impl Rng + CryptoRng, dec_key: DecryptionKey) {
         let message = &Scalar::from(123456789u32) * &GENERATOR_TABLE;
@@ -161,4 +528,286 @@ mod tests {
         assert_eq!(message, dec_key.decrypt(ct1));
         assert_eq!(message, dec_key.decrypt(ct2));
     }
+
+    #[rstest]
+    #[should_panic(expected = "same length")]
+    fn test_rerandomise_len_mismatch(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
+    ) {
+        ct2.pop();
+        rerandomise(&mut ct1, &mut ct2, dec_key.encryption_key(), &mut rng);
+    }
+
+    #[rstest]
+    fn test_rerandomise_to(
+        ct1: Vec<Ciphertext>,
+        ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
+    ) {
+        let prev_ct1 = ct1.clone();
+        let prev_ct2 = ct2.clone();
+
+        let (new_ct1, new_ct2) = rerandomise_to(&ct1, &ct2, dec_key.encryption_key(), &mut rng);
+
+        assert_eq!(prev_ct1, ct1);
+        assert_eq!(prev_ct2, ct2);
+        assert!(zip(&ct1, &new_ct1).all(|(prev, new)| prev != new));
+        assert!(testing::is_rerandomisation_of(&dec_key, &ct1, &new_ct1));
+        assert!(testing::is_rerandomisation_of(&dec_key, &ct2, &new_ct2));
+    }
+
+    #[rstest]
+    fn test_rerandomise_n(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        let enc_key = dec_key.encryption_key();
+        let prev_ct1 = ct1.clone();
+
+        let mut single = ct1.clone();
+        rerandomise(&mut single, &mut ct2.clone(), enc_key, &mut rng());
+        let mut single_n = ct1.clone();
+        rerandomise_n(&mut single_n, &mut ct2.clone(), enc_key, 1, &mut rng());
+        assert_eq!(single, single_n);
+
+        rerandomise_n(&mut ct1, &mut ct2, enc_key, 3, &mut rng());
+
+        assert_ne!(single, ct1);
+        for (prev, ct) in zip(&prev_ct1, &ct1) {
+            assert_ne!(prev, ct);
+            assert_eq!(dec_key.decrypt(*prev), dec_key.decrypt(*ct));
+        }
+        assert_eq!(ct1, ct2);
+    }
+
+    #[rstest]
+    fn test_rerandomise_selected(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
+    ) {
+        let prev_ct1 = ct1.clone();
+        let prev_ct2 = ct2.clone();
+        let even: Vec<_> = (0..N_SIZE).step_by(2).collect();
+
+        rerandomise_selected(
+            &mut ct1,
+            &mut ct2,
+            dec_key.encryption_key(),
+            &even,
+            &mut rng,
+        );
+
+        for i in 0..N_SIZE {
+            if i % 2 == 0 {
+                assert_ne!(prev_ct1[i], ct1[i]);
+                assert_ne!(prev_ct2[i], ct2[i]);
+            } else {
+                assert_eq!(prev_ct1[i], ct1[i]);
+                assert_eq!(prev_ct2[i], ct2[i]);
+            }
+            assert_eq!(dec_key.decrypt(prev_ct1[i]), dec_key.decrypt(ct1[i]));
+            assert_eq!(dec_key.decrypt(prev_ct2[i]), dec_key.decrypt(ct2[i]));
+        }
+    }
+
+    #[rstest]
+    #[should_panic]
+    fn test_rerandomise_selected_out_of_range(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
+    ) {
+        rerandomise_selected(
+            &mut ct1,
+            &mut ct2,
+            dec_key.encryption_key(),
+            &[N_SIZE],
+            &mut rng,
+        );
+    }
+
+    #[rstest]
+    #[should_panic(expected = "same length")]
+    fn test_rerandomise_selected_len_mismatch(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
+    ) {
+        ct2.pop();
+        rerandomise_selected(&mut ct1, &mut ct2, dec_key.encryption_key(), &[0], &mut rng);
+    }
+
+    #[rstest]
+    fn test_pad_odd(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+        dec_key: DecryptionKey,
+    ) {
+        let enc_key = dec_key.encryption_key();
+        assert!(!pad_odd(&mut ct1, &mut ct2, enc_key, &mut rng));
+        assert_eq!(ct1.len(), N_SIZE);
+
+        ct1.pop();
+        ct2.pop();
+        assert!(pad_odd(&mut ct1, &mut ct2, enc_key, &mut rng));
+        assert_eq!(ct1.len(), N_SIZE);
+        assert_eq!(ct2.len(), N_SIZE);
+        assert_eq!(dec_key.decrypt(ct1[N_SIZE - 1]), RistrettoPoint::identity());
+        assert_eq!(dec_key.decrypt(ct2[N_SIZE - 1]), RistrettoPoint::identity());
+
+        remix(&mut ct1, &mut ct2, enc_key);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "even length")]
+    fn test_remix_rejects_odd_len(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        ct1.pop();
+        ct2.pop();
+        remix(&mut ct1, &mut ct2, dec_key.encryption_key());
+    }
+
+    #[rstest]
+    fn test_shuffle_pairs_resumable(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let start = ShuffleState::new(&mut rng);
+        let mut single_x = ct1.clone();
+        let mut single_y = ct2.clone();
+        assert!(shuffle_pairs_resumable(
+            &mut single_x,
+            &mut single_y,
+            &mut start.clone(),
+            usize::MAX
+        ));
+        assert_ne!(ct1, single_x);
+
+        let half = N_SIZE / 4;
+        let mut state = start;
+        assert!(!shuffle_pairs_resumable(
+            &mut ct1, &mut ct2, &mut state, half
+        ));
+        assert_eq!(state.step, half);
+
+        // Rebuild the state from its persisted fields, as after a crash
+        let (seed, step) = (state.seed, state.step);
+        let mut resumed = ShuffleState { seed, step };
+        assert!(shuffle_pairs_resumable(
+            &mut ct1,
+            &mut ct2,
+            &mut resumed,
+            usize::MAX
+        ));
+
+        assert_eq!(single_x, ct1);
+        assert_eq!(single_y, ct2);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "past the last step")]
+    fn test_shuffle_pairs_resumable_stale_state(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let mut state = ShuffleState {
+            step: 100,
+            ..ShuffleState::new(&mut rng)
+        };
+        shuffle_pairs_resumable(&mut ct1, &mut ct2, &mut state, usize::MAX);
+    }
+
+    #[rstest]
+    fn test_is_degenerate(ct1: Vec<Ciphertext>, dec_key: DecryptionKey) {
+        assert!(!is_degenerate(&ct1));
+        assert!(!is_degenerate(&[]));
+        assert!(is_degenerate(&[Ciphertext::identity(); N_SIZE]));
+        assert!(is_degenerate(&[ct1[0]; N_SIZE]));
+
+        let no_randomness: Vec<_> = (0..N_SIZE)
+            .map(|i| {
+                dec_key
+                    .encryption_key()
+                    .encrypt_with(&Scalar::from(i as u32) * &GENERATOR_TABLE, Scalar::zero())
+            })
+            .collect();
+        assert!(is_degenerate(&no_randomness));
+    }
+
+    #[rstest]
+    fn test_shuffle_pairs_with_anchors(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let prev_ct = ct1.clone();
+        let anchors = [0, 6, N_SIZE - 2];
+
+        shuffle_pairs_with_anchors(&mut ct1, &mut ct2, &anchors, &mut rng);
+
+        assert_eq!(ct1, ct2);
+        assert_ne!(prev_ct, ct1);
+        for anchor in anchors {
+            assert_eq!(prev_ct[anchor..anchor + 2], ct1[anchor..anchor + 2]);
+        }
+        for pair in ct1.chunks_exact(2) {
+            assert!(prev_ct.chunks_exact(2).any(|prev| prev == pair));
+        }
+    }
+
+    #[rstest]
+    #[should_panic(expected = "not pair-aligned")]
+    fn test_shuffle_pairs_with_unaligned_anchor(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        shuffle_pairs_with_anchors(&mut ct1, &mut ct2, &[3], &mut rng);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "out of range")]
+    fn test_shuffle_pairs_with_out_of_range_anchor(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        shuffle_pairs_with_anchors(&mut ct1, &mut ct2, &[N_SIZE], &mut rng);
+    }
+
+    #[rstest]
+    fn test_shuffle_pairs_with_tape(mut ct1: Vec<Ciphertext>, mut ct2: Vec<Ciphertext>) {
+        let mut expected_x = ct1.clone();
+        let mut expected_y = ct2.clone();
+        shuffle_pairs(&mut expected_x, &mut expected_y, &mut rng());
+
+        let tape = shuffle_tape(ct1.len(), &mut rng());
+        shuffle_pairs_with_tape(&mut ct1, &mut ct2, &tape);
+
+        assert_eq!(expected_x, ct1);
+        assert_eq!(expected_y, ct2);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "out of range")]
+    fn test_shuffle_pairs_with_invalid_tape(mut ct1: Vec<Ciphertext>, mut ct2: Vec<Ciphertext>) {
+        let mut tape = shuffle_tape(ct1.len(), &mut rng());
+        tape[3] = 2;
+        shuffle_pairs_with_tape(&mut ct1, &mut ct2, &tape);
+    }
 }
@@ -1,17 +1,28 @@
-use rand::Rng;
 use rayon::prelude::*;
 use rust_elgamal::{Ciphertext, EncryptionKey, Scalar};
 
+use crate::RemixRng;
+
 /// Same as [rerandomise](fn@super::rerandomise) but in parallel using [`rayon`].
 pub fn rerandomise(
     x_cipher: &mut [Ciphertext],
     y_cipher: &mut [Ciphertext],
     enc_key: &EncryptionKey,
 ) {
+    rerandomise_with_rng(x_cipher, y_cipher, enc_key, rand::thread_rng);
+}
+
+/// Same as [`rerandomise`] but each [`rayon`] task draws its randomness from an instance created by `new_rng`.
+pub fn rerandomise_with_rng<R: RemixRng>(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+    new_rng: impl Fn() -> R + Sync + Send,
+) {
+    super::assert_same_len(x_cipher, y_cipher);
     let x_iter = x_cipher.par_iter_mut();
     let y_iter = y_cipher.par_iter_mut();
-    x_iter.zip(y_iter).for_each(|(x, y)| {
-        let mut rng = rand::thread_rng();
+    x_iter.zip(y_iter).for_each_init(new_rng, |rng, (x, y)| {
         let r = Scalar::from(rng.gen::<u32>());
         *x = enc_key.rerandomise_with(*x, r);
         *y = enc_key.rerandomise_with(*y, r);
@@ -20,20 +31,45 @@ pub fn rerandomise(
 
 /// Same as [remix](fn@super::remix) but uses parallel [`rerandomise`].
 pub fn remix(x_cipher: &mut [Ciphertext], y_cipher: &mut [Ciphertext], enc_key: &EncryptionKey) {
-    let mut rng = rand::thread_rng();
+    remix_with_rng(x_cipher, y_cipher, enc_key, rand::thread_rng);
+}
+
+/// Same as [`remix`] but every source of randomness is an instance created by `new_rng`, see
+/// [`rerandomise_with_rng`].
+pub fn remix_with_rng<R: RemixRng>(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+    new_rng: impl Fn() -> R + Sync + Send,
+) {
+    super::assert_even_len(x_cipher, y_cipher);
+    let mut rng = new_rng();
     super::shuffle_pairs(x_cipher, y_cipher, &mut rng);
     super::shuffle_bits(x_cipher, y_cipher, &mut rng);
-    rerandomise(x_cipher, y_cipher, enc_key);
+    rerandomise_with_rng(x_cipher, y_cipher, enc_key, new_rng);
+}
+
+/// Same as [`remix`] but runs on the given [`rayon::ThreadPool`] instead of the global one, e.g. to pin the number of
+/// threads.
+pub fn remix_in_pool(
+    x_cipher: &mut [Ciphertext],
+    y_cipher: &mut [Ciphertext],
+    enc_key: &EncryptionKey,
+    pool: &rayon::ThreadPool,
+) {
+    pool.install(|| remix(x_cipher, y_cipher, enc_key));
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use rand::{CryptoRng, RngCore};
     use rstest::rstest;
     use rust_elgamal::{DecryptionKey, RistrettoPoint, GENERATOR_TABLE};
 
     use super::*;
-
-    const N_SIZE: usize = 32;
+    use crate::testing::fixtures::*;
 
     #[rstest]
     fn test_par_rerandomise() {
@@ -68,4 +104,92 @@ mod tests {
             ct2.iter().map(&mut decrypt)
         ));
     }
+
+    #[rstest]
+    fn test_remix_in_pool(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap();
+        let prev_ct1 = ct1.clone();
+
+        remix_in_pool(&mut ct1, &mut ct2, dec_key.encryption_key(), &pool);
+
+        assert_ne!(prev_ct1, ct1);
+        let ones = |ct: &[Ciphertext]| {
+            ct.iter()
+                .filter(|ct| dec_key.decrypt(**ct) == GENERATOR_TABLE.basepoint())
+                .count()
+        };
+        assert_eq!(ones(&ct1), N_SIZE / 2);
+        assert_eq!(ones(&ct2), N_SIZE / 2);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "same length")]
+    fn test_par_rerandomise_len_mismatch(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        ct2.pop();
+        rerandomise(&mut ct1, &mut ct2, dec_key.encryption_key());
+    }
+
+    /// Wraps an RNG and counts the bytes drawn from it, standing in for an HSM-backed generator.
+    struct CountingRng<'a> {
+        inner: rand::rngs::ThreadRng,
+        drawn: &'a AtomicUsize,
+    }
+
+    impl RngCore for CountingRng<'_> {
+        fn next_u32(&mut self) -> u32 {
+            self.drawn.fetch_add(4, Ordering::Relaxed);
+            self.inner.next_u32()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.drawn.fetch_add(8, Ordering::Relaxed);
+            self.inner.next_u64()
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.drawn.fetch_add(dest.len(), Ordering::Relaxed);
+            self.inner.fill_bytes(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.drawn.fetch_add(dest.len(), Ordering::Relaxed);
+            self.inner.try_fill_bytes(dest)
+        }
+    }
+
+    impl CryptoRng for CountingRng<'_> {}
+
+    #[rstest]
+    fn test_rerandomise_with_rng(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        let drawn = AtomicUsize::new(0);
+        let new_rng = || CountingRng {
+            inner: rand::thread_rng(),
+            drawn: &drawn,
+        };
+
+        let prev_ct1 = ct1.clone();
+
+        rerandomise_with_rng(&mut ct1, &mut ct2, dec_key.encryption_key(), new_rng);
+
+        assert_eq!(drawn.load(Ordering::Relaxed), N_SIZE * 4);
+        assert_ne!(prev_ct1, ct1);
+        assert!(crate::testing::is_rerandomisation_of(
+            &dec_key, &prev_ct1, &ct1
+        ));
+    }
 }
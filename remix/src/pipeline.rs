@@ -0,0 +1,147 @@
+use rust_elgamal::{Ciphertext, EncryptionKey};
+
+use crate::RemixRng;
+
+/// A single procedure of the re-mixing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// See [`shuffle_pairs`](fn@super::shuffle_pairs).
+    ShufflePairs,
+    /// See [`shuffle_bits`](fn@super::shuffle_bits).
+    ShuffleBits,
+    /// See [`rerandomise`](fn@super::rerandomise).
+    Rerandomise,
+}
+
+/// Builder of a custom re-mixing, running the chosen [`Phase`]s in the order they were added.
+/// [remix](fn@super::remix) is the [`Pipeline::remix`] preset.
+///
+/// ```
+/// # use rand::SeedableRng;
+/// # use rust_elgamal::DecryptionKey;
+/// # let dec_key = DecryptionKey::new(&mut rand::rngs::StdRng::seed_from_u64(7));
+/// # let (mut x_cipher, mut y_cipher) = (vec![], vec![]);
+/// remix::pipeline::Pipeline::new()
+///     .shuffle_pairs()
+///     .rerandomise()
+///     .shuffle_bits()
+///     .run(&mut x_cipher, &mut y_cipher, dec_key.encryption_key());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline {
+    phases: Vec<Phase>,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline, which leaves the ciphertexts untouched.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The standard re-mixing: [`Phase::ShufflePairs`], [`Phase::ShuffleBits`], [`Phase::Rerandomise`].
+    pub fn remix() -> Self {
+        Self::new().shuffle_pairs().shuffle_bits().rerandomise()
+    }
+
+    pub fn phase(mut self, phase: Phase) -> Self {
+        self.phases.push(phase);
+        self
+    }
+
+    pub fn shuffle_pairs(self) -> Self {
+        self.phase(Phase::ShufflePairs)
+    }
+
+    pub fn shuffle_bits(self) -> Self {
+        self.phase(Phase::ShuffleBits)
+    }
+
+    pub fn rerandomise(self) -> Self {
+        self.phase(Phase::Rerandomise)
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
+    }
+
+    /// Runs every phase in order using [`rand::thread_rng`].
+    ///
+    /// # Panics
+    /// See [`Pipeline::run_with_rng`].
+    pub fn run(
+        &self,
+        x_cipher: &mut [Ciphertext],
+        y_cipher: &mut [Ciphertext],
+        enc_key: &EncryptionKey,
+    ) {
+        self.run_with_rng(x_cipher, y_cipher, enc_key, &mut rand::thread_rng());
+    }
+
+    /// Same as [`Pipeline::run`] but drawing the randomness from `rng`.
+    ///
+    /// # Panics
+    /// If the pipeline has a [`Phase::ShuffleBits`] and the slices have an odd length, use
+    /// [`pad_odd`](fn@super::pad_odd) first for those.
+    pub fn run_with_rng(
+        &self,
+        x_cipher: &mut [Ciphertext],
+        y_cipher: &mut [Ciphertext],
+        enc_key: &EncryptionKey,
+        rng: &mut impl RemixRng,
+    ) {
+        if self.phases.contains(&Phase::ShuffleBits) {
+            super::assert_even_len(x_cipher, y_cipher);
+        }
+        for phase in &self.phases {
+            match phase {
+                Phase::ShufflePairs => super::shuffle_pairs(x_cipher, y_cipher, rng),
+                Phase::ShuffleBits => super::shuffle_bits(x_cipher, y_cipher, rng),
+                Phase::Rerandomise => super::rerandomise(x_cipher, y_cipher, enc_key, rng),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use rust_elgamal::DecryptionKey;
+
+    use super::*;
+    use crate::testing::{assert_multiset_preserved, fixtures::*};
+
+    #[rstest]
+    #[case(Pipeline::remix())]
+    #[case(Pipeline::new().rerandomise().shuffle_bits().shuffle_pairs())]
+    #[case(Pipeline::new().shuffle_bits().rerandomise())]
+    #[case(Pipeline::new())]
+    fn test_pipeline_preserves_plaintexts(
+        #[case] pipeline: Pipeline,
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        let prev_ct = ct1.clone();
+
+        pipeline.run(&mut ct1, &mut ct2, dec_key.encryption_key());
+
+        assert_multiset_preserved(&dec_key, &prev_ct, &ct1);
+        assert_multiset_preserved(&dec_key, &prev_ct, &ct2);
+        assert!(Iterator::eq(
+            ct1.iter().map(|ct| dec_key.decrypt(*ct)),
+            ct2.iter().map(|ct| dec_key.decrypt(*ct))
+        ));
+    }
+
+    #[rstest]
+    #[should_panic(expected = "even length")]
+    fn test_pipeline_rejects_odd_len(
+        mut ct1: Vec<Ciphertext>,
+        mut ct2: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+    ) {
+        ct1.pop();
+        ct2.pop();
+        Pipeline::remix().run(&mut ct1, &mut ct2, dec_key.encryption_key());
+    }
+}
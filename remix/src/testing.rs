@@ -0,0 +1,119 @@
+//! Helpers to check the correctness of re-mixing in tests, available with the `testing` feature.
+
+use std::iter::zip;
+
+use rust_elgamal::{Ciphertext, DecryptionKey};
+
+/// Checks whether every [`Ciphertext`] in `b` decrypts to the same plaintext as the one at the same position in `a`,
+/// i.e. `b` is a rerandomisation of `a` under the key of `dec_key`.
+pub fn is_rerandomisation_of(dec_key: &DecryptionKey, a: &[Ciphertext], b: &[Ciphertext]) -> bool {
+    a.len() == b.len() && zip(a, b).all(|(a, b)| dec_key.decrypt(*a) == dec_key.decrypt(*b))
+}
+
+/// Asserts that `after` decrypts to the same multiset of plaintexts as `before`, ignoring their order, which is the
+/// invariant every re-mixing must keep.
+///
+/// # Panics
+/// If the plaintexts differ.
+pub fn assert_multiset_preserved(
+    dec_key: &DecryptionKey,
+    before: &[Ciphertext],
+    after: &[Ciphertext],
+) {
+    let decrypt_sorted = |cipher: &[Ciphertext]| {
+        let mut plain: Vec<_> = cipher
+            .iter()
+            .map(|ct| dec_key.decrypt(*ct).compress().to_bytes())
+            .collect();
+        plain.sort_unstable();
+        plain
+    };
+    assert!(
+        decrypt_sorted(before) == decrypt_sorted(after),
+        "re-mixing didn't preserve the multiset of plaintexts"
+    );
+}
+
+/// Fixtures shared by the tests of every module.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use rand::{rngs::StdRng, CryptoRng, Rng, SeedableRng};
+    use rstest::fixture;
+    use rust_elgamal::{Ciphertext, DecryptionKey, Scalar, GENERATOR_TABLE};
+
+    pub(crate) const N_SIZE: usize = 32;
+
+    #[fixture]
+    pub(crate) fn rng() -> impl Rng + CryptoRng {
+        StdRng::seed_from_u64(7)
+    }
+
+    #[fixture]
+    pub(crate) fn dec_key() -> DecryptionKey {
+        let mut rng = rng();
+        DecryptionKey::new(&mut rng)
+    }
+
+    #[fixture]
+    pub(crate) fn ct1() -> Vec<Ciphertext> {
+        let mut rng = rng();
+        let dec_key = dec_key();
+        let enc_key = dec_key.encryption_key();
+
+        (0..N_SIZE)
+            .map(|i| enc_key.encrypt(&Scalar::from((i % 2) as u8) * &GENERATOR_TABLE, &mut rng))
+            .collect()
+    }
+
+    #[fixture]
+    pub(crate) fn ct2() -> Vec<Ciphertext> {
+        ct1() // a clone for now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{CryptoRng, Rng};
+    use rstest::rstest;
+
+    use super::{fixtures::*, *};
+
+    #[rstest]
+    fn test_is_rerandomisation_of(
+        ct1: Vec<Ciphertext>,
+        dec_key: DecryptionKey,
+        mut rng: impl Rng + CryptoRng,
+    ) {
+        let mut rerandomised = ct1.clone();
+        crate::rerandomise(
+            &mut rerandomised,
+            &mut ct1.clone(),
+            dec_key.encryption_key(),
+            &mut rng,
+        );
+        let mut reversed = rerandomised.clone();
+        reversed.reverse();
+
+        assert_ne!(ct1, rerandomised);
+        assert!(is_rerandomisation_of(&dec_key, &ct1, &rerandomised));
+        assert!(!is_rerandomisation_of(&dec_key, &ct1, &reversed));
+        assert!(!is_rerandomisation_of(&dec_key, &ct1, &rerandomised[1..]));
+    }
+
+    #[rstest]
+    fn test_assert_multiset_preserved(ct1: Vec<Ciphertext>, dec_key: DecryptionKey) {
+        let mut remixed = ct1.clone();
+        crate::remix(&mut remixed, &mut ct1.clone(), dec_key.encryption_key());
+
+        assert_multiset_preserved(&dec_key, &ct1, &remixed);
+    }
+
+    #[rstest]
+    #[should_panic(expected = "didn't preserve")]
+    fn test_assert_multiset_preserved_fails(ct1: Vec<Ciphertext>, dec_key: DecryptionKey) {
+        let mut tampered = ct1.clone();
+        tampered[0] = tampered[1];
+
+        assert_multiset_preserved(&dec_key, &ct1, &tampered);
+    }
+}
@@ -0,0 +1,27 @@
+use bitvec::prelude::*;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+use rust_elgamal::DecryptionKey;
+
+const N_SIZE: usize = 25600;
+
+fn bench_encrypt_run(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Encrypt run");
+    group.sample_size(20);
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let dec_key = DecryptionKey::new(&mut rng);
+    let enc_key = dec_key.encryption_key();
+    let bits = bitvec![u8, Lsb0; 1; N_SIZE];
+
+    group.bench_function("encrypt_bits", |b| {
+        b.iter(|| worldcoin::encrypt_bits(&bits, enc_key, &mut rng).count())
+    });
+
+    group.bench_function("encrypt_run", |b| {
+        b.iter(|| worldcoin::encrypt_run(true, N_SIZE, enc_key, &mut rng).count())
+    });
+}
+
+criterion_group!(benches, bench_encrypt_run);
+criterion_main!(benches);
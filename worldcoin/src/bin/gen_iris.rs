@@ -0,0 +1,33 @@
+//! Generates a reproducible pair of encoded iris codes with a given logical hamming distance.
+//!
+//! Usage: `gen_iris <len> <distance> <seed>`
+//!
+//! Prints both codes as base64 of their bytes in [`Lsb0`](bitvec::order::Lsb0) order, one per line.
+
+use std::{env, process};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn main() {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let parse = |idx: usize, name: &str| -> u64 {
+        args.get(idx)
+            .and_then(|arg| arg.parse().ok())
+            .unwrap_or_else(|| {
+                eprintln!("invalid or missing <{name}>\nusage: gen_iris <len> <distance> <seed>");
+                process::exit(1);
+            })
+    };
+    let len = parse(0, "len") as usize;
+    let distance = parse(1, "distance") as usize;
+    let seed = parse(2, "seed");
+    if distance > len {
+        eprintln!("distance {distance} exceeds the length {len}");
+        process::exit(1);
+    }
+
+    let (x, y) = worldcoin::gen_code_pair(len, distance, &mut StdRng::seed_from_u64(seed));
+    println!("{}", STANDARD.encode(x.as_raw_slice()));
+    println!("{}", STANDARD.encode(y.as_raw_slice()));
+}
@@ -0,0 +1,93 @@
+use bitvec::prelude::*;
+
+use crate::{DecodeError, LengthMismatch};
+
+/// An iris code made of a data channel and a mask channel of the same length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrisCode {
+    data: BitVec,
+    mask: BitVec,
+}
+
+impl IrisCode {
+    pub fn new(data: BitVec, mask: BitVec) -> Result<Self, LengthMismatch> {
+        if data.len() != mask.len() {
+            return Err(LengthMismatch {
+                left: data.len(),
+                right: mask.len(),
+            });
+        }
+        Ok(Self { data, mask })
+    }
+
+    /// Splits bits laid out as `data[0], mask[0], data[1], mask[1], ...` into both channels.
+    pub fn from_interleaved<T: BitStore, O: BitOrder>(
+        bits: &BitSlice<T, O>,
+    ) -> Result<Self, DecodeError> {
+        if !bits.len().is_multiple_of(2) {
+            return Err(DecodeError::OddLength(bits.len()));
+        }
+        let data = bits.iter().by_vals().step_by(2).collect();
+        let mask = bits.iter().by_vals().skip(1).step_by(2).collect();
+        Ok(Self { data, mask })
+    }
+
+    /// Inverse of [`IrisCode::from_interleaved`].
+    pub fn to_interleaved(&self) -> BitVec {
+        self.data
+            .iter()
+            .by_vals()
+            .zip(self.mask.iter().by_vals())
+            .flat_map(|(data, mask)| [data, mask])
+            .collect()
+    }
+
+    pub fn data(&self) -> &BitSlice {
+        &self.data
+    }
+
+    pub fn mask(&self) -> &BitSlice {
+        &self.mask
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn into_parts(self) -> (BitVec, BitVec) {
+        (self.data, self.mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_validates_lengths() {
+        assert!(IrisCode::new(bitvec![0, 1, 1], bitvec![1, 1, 1]).is_ok());
+        assert_eq!(
+            IrisCode::new(bitvec![0, 1, 1], bitvec![1, 1]),
+            Err(LengthMismatch { left: 3, right: 2 })
+        );
+    }
+
+    #[test]
+    fn test_interleave_round_trip() {
+        let bits = BitVec::<u8, Lsb0>::from_slice(&[0b11100100, 0b00011011]);
+
+        let code = IrisCode::from_interleaved(&bits).unwrap();
+
+        assert_eq!(code.data(), bits![0, 1, 0, 1, 1, 0, 1, 0]);
+        assert_eq!(code.mask(), bits![0, 0, 1, 1, 1, 1, 0, 0]);
+        assert_eq!(code.to_interleaved(), bits);
+        assert_eq!(
+            IrisCode::from_interleaved(&bits[1..]),
+            Err(DecodeError::OddLength(15))
+        );
+    }
+}
@@ -0,0 +1,694 @@
+//! Encoding and encryption helpers for iris codes, shared by the playground and tests.
+
+use std::{
+    fmt,
+    iter::{once, zip},
+    sync::Mutex,
+};
+
+use bitvec::prelude::*;
+use rand::{CryptoRng, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use rust_elgamal::{Ciphertext, DecryptionKey, EncryptionKey, Scalar, GENERATOR_TABLE};
+
+mod iris;
+
+pub use iris::IrisCode;
+
+/// Error produced when bits don't follow the [encoding](encode_bits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The encoded bits have an odd length so they can't be split into pairs.
+    OddLength(usize),
+    /// The pair at the given index is `00` or `11`.
+    InvalidPair(usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OddLength(len) => write!(f, "encoded bits have odd length {len}"),
+            Self::InvalidPair(idx) => write!(f, "pair {idx} is not a valid encoding of a bit"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Error produced when two sequences that must have the same length don't.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub left: usize,
+    pub right: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "lengths {} and {} differ", self.left, self.right)
+    }
+}
+
+impl std::error::Error for LengthMismatch {}
+
+/// Encodes every bit into a pair of bits, `0` becomes `01` and `1` becomes `10`.
+pub fn encode_bits<T: BitStore, O: BitOrder>(
+    bits: &BitSlice<T, O>,
+) -> impl Iterator<Item = bool> + '_ {
+    bits.iter().flat_map(|bit| {
+        let encoding = match *bit {
+            false /*0*/ => (false, true) /*01*/,
+            true  /*1*/ => (true, false) /*10*/,
+        };
+        once(encoding.0).chain(once(encoding.1))
+    })
+}
+
+/// Inverse of [`encode_bits`], decodes every pair of bits, `01` becomes `0` and `10` becomes `1`.
+///
+/// # Errors
+/// If the encoded bits have an odd length or contain a `00` or `11` pair.
+pub fn decode_bits<T: BitStore, O: BitOrder>(
+    encoded: &BitSlice<T, O>,
+) -> Result<BitVec, DecodeError> {
+    if !encoded.len().is_multiple_of(2) {
+        return Err(DecodeError::OddLength(encoded.len()));
+    }
+    encoded
+        .chunks_exact(2)
+        .enumerate()
+        .map(|(idx, pair)| decode_pair(pair, idx))
+        .collect()
+}
+
+/// Encrypts every bit as the [`Ciphertext`] of either the identity or the generator.
+pub fn encrypt_bits<'a, T: BitStore, O: BitOrder>(
+    bits: &'a BitSlice<T, O>,
+    ek: &'a EncryptionKey,
+    rng: &'a mut (impl Rng + CryptoRng + 'static),
+) -> impl Iterator<Item = Ciphertext> + 'a {
+    bits.iter()
+        .map(|bit| ek.encrypt(&Scalar::from(*bit as u32) * &GENERATOR_TABLE, rng))
+}
+
+/// Encrypts a pair of codes with [`encrypt_bits`], ready to be re-mixed together.
+///
+/// # Errors
+/// If the codes have different lengths.
+pub fn encrypt_codes<T: BitStore, O: BitOrder>(
+    x_bits: &BitSlice<T, O>,
+    y_bits: &BitSlice<T, O>,
+    ek: &EncryptionKey,
+    rng: &mut (impl Rng + CryptoRng + 'static),
+) -> Result<(Vec<Ciphertext>, Vec<Ciphertext>), LengthMismatch> {
+    if x_bits.len() != y_bits.len() {
+        return Err(LengthMismatch {
+            left: x_bits.len(),
+            right: y_bits.len(),
+        });
+    }
+    let x_cipher = encrypt_bits(x_bits, ek, rng).collect();
+    let y_cipher = encrypt_bits(y_bits, ek, rng).collect();
+    Ok((x_cipher, y_cipher))
+}
+
+/// Same as [`encrypt_bits`] but reads the bits straight from packed `u64` words, without building a [`BitVec`].
+/// Only the first `bit_len` bits are encrypted, in [`Lsb0`] order.
+///
+/// # Panics
+/// If `bit_len` is greater than the number of bits in `words`.
+pub fn encrypt_words<'a>(
+    words: &'a [u64],
+    bit_len: usize,
+    ek: &'a EncryptionKey,
+    rng: &'a mut (impl Rng + CryptoRng + 'static),
+) -> impl Iterator<Item = Ciphertext> + 'a {
+    let bits = words.view_bits::<Lsb0>();
+    assert!(
+        bit_len <= bits.len(),
+        "bit_len {bit_len} exceeds the {} bits available",
+        bits.len()
+    );
+    encrypt_bits(&bits[..bit_len], ek, rng)
+}
+
+/// Same as [`encrypt_bits`] but encrypts chunks of bits in parallel using [`rayon`], calling `on_progress` with the
+/// number of bits encrypted so far and the total after every chunk. The counts are reported in increasing order.
+pub fn encrypt_with_progress<T: BitStore + Sync, O: BitOrder>(
+    bits: &BitSlice<T, O>,
+    ek: &EncryptionKey,
+    on_progress: impl Fn(usize, usize) + Sync,
+) -> Vec<Ciphertext> {
+    const CHUNK_SIZE: usize = 256;
+    let total = bits.len();
+    let done = Mutex::new(0);
+    (0..total.div_ceil(CHUNK_SIZE))
+        .into_par_iter()
+        .flat_map_iter(|chunk_idx| {
+            let start = chunk_idx * CHUNK_SIZE;
+            let chunk = &bits[start..total.min(start + CHUNK_SIZE)];
+            let ct: Vec<_> = encrypt_bits(chunk, ek, &mut rand::thread_rng()).collect();
+
+            let mut done = done.lock().unwrap();
+            *done += chunk.len();
+            on_progress(*done, total);
+            ct
+        })
+        .collect()
+}
+
+/// Encrypts every bit like [`encrypt_bits`] but with nonces derived from `nonce_seed` by the ChaCha20 stream cipher,
+/// so the same seed always produces the same [`Ciphertext`]s.
+///
+/// This is meant for reproducible test data, such as deterministic test galleries. It is NOT semantically secure,
+/// anyone who knows the seed can tell which bits were encrypted, so never use it for real codes.
+pub fn encrypt_deterministic<T: BitStore, O: BitOrder>(
+    bits: &BitSlice<T, O>,
+    ek: &EncryptionKey,
+    nonce_seed: u64,
+) -> Vec<Ciphertext> {
+    let mut kdf = ChaCha20Rng::seed_from_u64(nonce_seed);
+    bits.iter()
+        .by_vals()
+        .map(|bit| {
+            let m = &Scalar::from(bit as u32) * &GENERATOR_TABLE;
+            ek.encrypt_with(m, Scalar::random(&mut kdf))
+        })
+        .collect()
+}
+
+/// Encrypts a run of `count` identical bits. The bit is encrypted only once and every other [`Ciphertext`] is a
+/// rerandomisation of it, which skips mapping the bit to a point for each element.
+/// The run is not hidden, encrypting it takes less time than encrypting the same bits with [`encrypt_bits`], so only
+/// use it when the repeated bit is public, e.g. a constant mask.
+pub fn encrypt_run<'a>(
+    bit: bool,
+    count: usize,
+    ek: &'a EncryptionKey,
+    rng: &'a mut (impl Rng + CryptoRng + 'static),
+) -> impl Iterator<Item = Ciphertext> + 'a {
+    let m = &Scalar::from(bit as u32) * &GENERATOR_TABLE;
+    let mut base = None;
+    (0..count).map(move |_| match base {
+        Some(ct) => ek.rerandomise_with(ct, Scalar::random(rng)),
+        None => *base.insert(ek.encrypt(m, rng)),
+    })
+}
+
+/// Decrypts every [`Ciphertext`] into a bit, `false` if it decrypts to the identity and `true` otherwise.
+pub fn decrypt_bits<'a>(
+    ct: &'a [Ciphertext],
+    pk: &'a DecryptionKey,
+) -> impl Iterator<Item = bool> + 'a {
+    ct.iter().map(|ct| {
+        let point = pk.decrypt(*ct);
+        point != (&Scalar::from(0u32) * &GENERATOR_TABLE)
+    })
+}
+
+/// Homomorphically adds the [`Ciphertext`]s of `a` and `b` position-wise. Each result decrypts to the sum of both
+/// plaintexts in the group, e.g. encryptions of the bits `1` and `1` add up to an encryption of `2`.
+///
+/// # Errors
+/// If the slices have different lengths.
+pub fn add_codes(a: &[Ciphertext], b: &[Ciphertext]) -> Result<Vec<Ciphertext>, LengthMismatch> {
+    if a.len() != b.len() {
+        return Err(LengthMismatch {
+            left: a.len(),
+            right: b.len(),
+        });
+    }
+    Ok(zip(a, b).map(|(a, b)| a + b).collect())
+}
+
+/// Generates a random pair of [encoded](encode_bits) codes of `len` bits whose logical bits differ in exactly
+/// `distance` positions.
+///
+/// # Panics
+/// If `distance` is greater than `len`.
+pub fn gen_code_pair(
+    len: usize,
+    distance: usize,
+    rng: &mut impl Rng,
+) -> (BitVec<u8, Lsb0>, BitVec<u8, Lsb0>) {
+    assert!(
+        distance <= len,
+        "distance {distance} exceeds the length {len}"
+    );
+    let x: BitVec<u8, Lsb0> = (0..len).map(|_| rng.gen::<bool>()).collect();
+    let mut y = x.clone();
+    for idx in rand::seq::index::sample(rng, len, distance) {
+        let flipped = !y[idx];
+        y.set(idx, flipped);
+    }
+    (encode_bits(&x).collect(), encode_bits(&y).collect())
+}
+
+/// Sanity check for decrypted codes: returns `true` if the number of ones is within `margin` of either `0` or the
+/// length. A real iris code has a roughly balanced popcount, so an extreme one usually means the decryption failed.
+/// It's meant for the logical bits, an [encoded](encode_bits) code always has exactly half of its bits set.
+pub fn is_extreme_popcount<T: BitStore, O: BitOrder>(bits: &BitSlice<T, O>, margin: usize) -> bool {
+    let ones = bits.count_ones();
+    ones <= margin || ones >= bits.len().saturating_sub(margin)
+}
+
+/// Counts the positions where `x` and `y` differ.
+///
+/// # Panics
+/// If the bit slices have different lengths.
+pub fn hamming_distance<T1, O1, T2, O2>(x: &BitSlice<T1, O1>, y: &BitSlice<T2, O2>) -> usize
+where
+    T1: BitStore,
+    O1: BitOrder,
+    T2: BitStore,
+    O2: BitOrder,
+{
+    assert_eq!(x.len(), y.len(), "bit slices must have the same length");
+    zip(x.iter().by_vals(), y.iter().by_vals())
+        .filter(|(a, b)| a != b)
+        .count()
+}
+
+/// Same as [`hamming_distance`] but only compares the bits at `positions`, as a cheap approximation.
+///
+/// # Panics
+/// If a position is out of range for either bit slice.
+pub fn hamming_subset<T1, O1, T2, O2>(
+    x: &BitSlice<T1, O1>,
+    y: &BitSlice<T2, O2>,
+    positions: &[usize],
+) -> usize
+where
+    T1: BitStore,
+    O1: BitOrder,
+    T2: BitStore,
+    O2: BitOrder,
+{
+    positions.iter().filter(|&&idx| x[idx] != y[idx]).count()
+}
+
+/// Finds the code in `gallery` closest to `probe`, returning its index and [`hamming_distance`].
+/// Returns `None` for an empty gallery.
+pub fn best_match<T: BitStore, O: BitOrder>(
+    probe: &BitSlice<T, O>,
+    gallery: &[BitVec<T, O>],
+) -> Option<(usize, usize)> {
+    gallery
+        .iter()
+        .map(|code| hamming_distance(probe, code))
+        .enumerate()
+        .min_by_key(|&(_, distance)| distance)
+}
+
+/// Same as [`best_match`] but first discards the codes whose [`hamming_subset`] over `positions` exceeds
+/// `max_subset_distance`, and only computes the full distance for the remaining ones.
+/// Returns `None` if no code survives the pruning.
+pub fn best_match_two_stage<T: BitStore, O: BitOrder>(
+    probe: &BitSlice<T, O>,
+    gallery: &[BitVec<T, O>],
+    positions: &[usize],
+    max_subset_distance: usize,
+) -> Option<(usize, usize)> {
+    gallery
+        .iter()
+        .enumerate()
+        .filter(|(_, code)| hamming_subset(probe, code, positions) <= max_subset_distance)
+        .map(|(idx, code)| (idx, hamming_distance(probe, code)))
+        .min_by_key(|&(_, distance)| distance)
+}
+
+/// Computes the hamming distance between the bits that `x` and `y` [encode](encode_bits), so a flipped bit counts once
+/// instead of twice as [`hamming_distance`] on the encoded bits would.
+///
+/// # Errors
+/// If the encoded bits have an odd length or contain an invalid pair.
+///
+/// # Panics
+/// If the bit slices have different lengths.
+pub fn hamming_distance_encoded<T1, O1, T2, O2>(
+    x: &BitSlice<T1, O1>,
+    y: &BitSlice<T2, O2>,
+) -> Result<usize, DecodeError>
+where
+    T1: BitStore,
+    O1: BitOrder,
+    T2: BitStore,
+    O2: BitOrder,
+{
+    assert_eq!(x.len(), y.len(), "bit slices must have the same length");
+    if !x.len().is_multiple_of(2) {
+        return Err(DecodeError::OddLength(x.len()));
+    }
+    zip(x.chunks_exact(2), y.chunks_exact(2))
+        .enumerate()
+        .try_fold(0, |distance, (idx, (x_pair, y_pair))| {
+            let x_bit = decode_pair(x_pair, idx)?;
+            let y_bit = decode_pair(y_pair, idx)?;
+            Ok(distance + (x_bit != y_bit) as usize)
+        })
+}
+
+fn decode_pair<T: BitStore, O: BitOrder>(
+    pair: &BitSlice<T, O>,
+    idx: usize,
+) -> Result<bool, DecodeError> {
+    match (pair[0], pair[1]) {
+        (false, true) /*01*/ => Ok(false) /*0*/,
+        (true, false) /*10*/ => Ok(true)  /*1*/,
+        _ => Err(DecodeError::InvalidPair(idx)),
+    }
+}
+
+/// Decrypts both [`Ciphertext`] slices and computes their [`hamming_distance`] `window` ciphertexts at a time, so
+/// only one window of decrypted bits is held in memory at once.
+///
+/// # Panics
+/// If the slices have different lengths or `window` is zero.
+pub fn hamming_distance_windowed(
+    x_cipher: &[Ciphertext],
+    y_cipher: &[Ciphertext],
+    dec_key: &DecryptionKey,
+    window: usize,
+) -> usize {
+    assert_eq!(
+        x_cipher.len(),
+        y_cipher.len(),
+        "ciphertext slices must have the same length"
+    );
+    assert!(window > 0, "window must not be zero");
+    zip(x_cipher.chunks(window), y_cipher.chunks(window))
+        .map(|(x_chunk, y_chunk)| {
+            let x_bits: BitVec = decrypt_bits(x_chunk, dec_key).collect();
+            let y_bits: BitVec = decrypt_bits(y_chunk, dec_key).collect();
+            hamming_distance(&x_bits, &y_bits)
+        })
+        .sum()
+}
+
+/// Computes the [`hamming_distance`] between `x` and `y` circularly rotated by every offset in `rotations` and
+/// returns the minimum, which aligns two iris codes captured at slightly different angles.
+/// Both codes are expected to be [encoded](encode_bits), so an offset counts pairs of bits and a positive offset
+/// rotates `y` to the left.
+///
+/// # Panics
+/// If the bit slices have different or odd lengths, or `rotations` is empty.
+pub fn min_rotated_hamming<T: BitStore, O: BitOrder>(
+    x: &BitSlice<T, O>,
+    y: &BitSlice<T, O>,
+    rotations: &[i32],
+) -> usize {
+    assert!(
+        y.len().is_multiple_of(2),
+        "encoded bit slices must have even length"
+    );
+    assert!(!rotations.is_empty(), "at least one rotation is required");
+    let pairs = y.len() / 2;
+    rotations
+        .iter()
+        .map(|&rotation| {
+            let mut rotated = y.to_bitvec();
+            if pairs > 0 {
+                let shift = (rotation as i64).rem_euclid(pairs as i64) as usize * 2;
+                rotated.rotate_left(shift);
+            }
+            hamming_distance(x, &rotated)
+        })
+        .min()
+        .expect("rotations is not empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    #[test]
+    fn test_encode_bits() {
+        let bits = BitVec::<u8, Msb0>::from_slice(&[0b11100100]);
+        let expected = BitVec::<u8, Msb0>::from_slice(&[0b10101001, 0b01100101]);
+
+        let enc_bits: BitVec<u8, Lsb0> = encode_bits(&bits[..]).collect();
+
+        assert_eq!(enc_bits, expected);
+    }
+
+    #[test]
+    fn test_decode_bits() {
+        let bits = BitVec::<u8, Msb0>::from_slice(&[0b11100100]);
+        let encoded: BitVec = encode_bits(&bits).collect();
+
+        assert_eq!(decode_bits(&encoded), Ok(bits.iter().by_vals().collect()));
+        assert_eq!(
+            decode_bits(bits![0, 1, 1, 0, 1, 1, 0, 1]),
+            Err(DecodeError::InvalidPair(2))
+        );
+        assert_eq!(
+            decode_bits(bits![0, 1, 0, 0]),
+            Err(DecodeError::InvalidPair(1))
+        );
+        assert_eq!(decode_bits(bits![0, 1, 0]), Err(DecodeError::OddLength(3)));
+    }
+
+    #[test]
+    fn test_encrypt_codes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let (x, y) = gen_code_pair(64, 5, &mut rng);
+        let dec_key = DecryptionKey::new(&mut rng);
+        let enc_key = dec_key.encryption_key();
+
+        let (mut x_ct, mut y_ct) = encrypt_codes(&x, &y, enc_key, &mut rng).unwrap();
+        remix::remix(&mut x_ct, &mut y_ct, enc_key);
+
+        let x_dec: BitVec = decrypt_bits(&x_ct, &dec_key).collect();
+        let y_dec: BitVec = decrypt_bits(&y_ct, &dec_key).collect();
+        assert_eq!(hamming_distance_encoded(&x_dec, &y_dec), Ok(5));
+        assert_eq!(
+            encrypt_codes(&x, &y[2..], enc_key, &mut rng),
+            Err(LengthMismatch {
+                left: 128,
+                right: 126
+            })
+        );
+    }
+
+    #[test]
+    fn test_encrypt_words() {
+        const BIT_LEN: usize = 100;
+        let words = [0xDEADBEEF_0123ABCD_u64, 0x0F0F_F0F0_1234_5678];
+        let bits = &words.view_bits::<Lsb0>()[..BIT_LEN];
+        let bit_vec: BitVec<u64, Lsb0> = bits.to_bitvec();
+
+        let dec_key = DecryptionKey::new(&mut StdRng::seed_from_u64(7));
+        let enc_key = dec_key.encryption_key();
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let from_words: Vec<_> = encrypt_words(&words, BIT_LEN, enc_key, &mut rng).collect();
+        let mut rng = StdRng::seed_from_u64(42);
+        let from_bits: Vec<_> = encrypt_bits(&bit_vec, enc_key, &mut rng).collect();
+
+        assert_eq!(from_words.len(), BIT_LEN);
+        assert_eq!(from_words, from_bits);
+        assert!(Iterator::eq(
+            decrypt_bits(&from_words, &dec_key),
+            bits.iter().by_vals()
+        ));
+    }
+
+    #[test]
+    fn test_is_extreme_popcount() {
+        let code = BitVec::<u8, Lsb0>::from_slice(&StdRng::seed_from_u64(7).gen::<[u8; 32]>());
+
+        assert!(!is_extreme_popcount(&code, 16));
+        assert!(is_extreme_popcount(&bitvec![0; 256], 16));
+        assert!(is_extreme_popcount(&bitvec![1; 256], 16));
+        let mut almost_zero = bitvec![0; 256];
+        almost_zero[..16].fill(true);
+        assert!(is_extreme_popcount(&almost_zero, 16));
+        assert!(!is_extreme_popcount(&almost_zero, 15));
+    }
+
+    #[test]
+    fn test_min_rotated_hamming() {
+        let code = BitVec::<u8, Lsb0>::from_slice(&StdRng::seed_from_u64(7).gen::<[u8; 32]>());
+        let x: BitVec<u8, Lsb0> = encode_bits(&code).collect();
+        let mut y = x.clone();
+        y.rotate_right(3 * 2);
+
+        assert_ne!(hamming_distance(&x, &y), 0);
+        assert_eq!(min_rotated_hamming(&x, &y, &[3]), 0);
+        assert_ne!(min_rotated_hamming(&x, &y, &[-3]), 0);
+        assert_eq!(min_rotated_hamming(&x, &y, &[-4, -2, 0, 3, 5]), 0);
+        assert_eq!(min_rotated_hamming(&x, &y, &[3 - 256]), 0);
+    }
+
+    #[test]
+    fn test_encrypt_bits_with_runs() {
+        let bits = bitvec![u8, Lsb0; 0, 0, 0, 1, 1, 0, 1, 1, 1, 1];
+        let mut rng = StdRng::seed_from_u64(7);
+        let dec_key = DecryptionKey::new(&mut rng);
+
+        let ct: Vec<_> = encrypt_bits(&bits, dec_key.encryption_key(), &mut rng).collect();
+
+        assert!(ct.windows(2).all(|w| w[0] != w[1]));
+        assert!(Iterator::eq(
+            decrypt_bits(&ct, &dec_key),
+            bits.iter().by_vals()
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_with_progress() {
+        let bits = BitVec::<u8, Lsb0>::from_slice(&StdRng::seed_from_u64(7).gen::<[u8; 200]>());
+        let dec_key = DecryptionKey::new(&mut StdRng::seed_from_u64(7));
+        let progress = Mutex::new(Vec::new());
+
+        let ct = encrypt_with_progress(&bits, dec_key.encryption_key(), |done, total| {
+            progress.lock().unwrap().push((done, total))
+        });
+
+        let progress = progress.into_inner().unwrap();
+        assert!(progress.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(progress.iter().all(|&(_, total)| total == bits.len()));
+        assert_eq!(progress.last(), Some(&(bits.len(), bits.len())));
+        assert!(Iterator::eq(
+            decrypt_bits(&ct, &dec_key),
+            bits.iter().by_vals()
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_deterministic() {
+        let bits = BitVec::<u8, Lsb0>::from_slice(&StdRng::seed_from_u64(7).gen::<[u8; 8]>());
+        let dec_key = DecryptionKey::new(&mut StdRng::seed_from_u64(7));
+        let enc_key = dec_key.encryption_key();
+
+        let ct = encrypt_deterministic(&bits, enc_key, 42);
+
+        assert_eq!(ct, encrypt_deterministic(&bits, enc_key, 42));
+        assert_ne!(ct, encrypt_deterministic(&bits, enc_key, 43));
+        assert!(ct.windows(2).all(|w| w[0] != w[1]));
+        assert!(Iterator::eq(
+            decrypt_bits(&ct, &dec_key),
+            bits.iter().by_vals()
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_run() {
+        const COUNT: usize = 16;
+        let mut rng = StdRng::seed_from_u64(7);
+        let dec_key = DecryptionKey::new(&mut rng);
+        let enc_key = dec_key.encryption_key();
+
+        for bit in [false, true] {
+            let ct: Vec<_> = encrypt_run(bit, COUNT, enc_key, &mut rng).collect();
+
+            assert_eq!(ct.len(), COUNT);
+            assert!(ct.windows(2).all(|w| w[0] != w[1]));
+            assert!(decrypt_bits(&ct, &dec_key).all(|b| b == bit));
+        }
+        assert_eq!(encrypt_run(true, 0, enc_key, &mut rng).count(), 0);
+    }
+
+    #[test]
+    fn test_hamming_distance_windowed() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let x = BitVec::<u8, Lsb0>::from_slice(&rng.gen::<[u8; 16]>());
+        let y = BitVec::<u8, Lsb0>::from_slice(&rng.gen::<[u8; 16]>());
+        let dec_key = DecryptionKey::new(&mut rng);
+        let enc_key = dec_key.encryption_key();
+
+        let x_ct: Vec<_> = encrypt_bits(&x, enc_key, &mut rng).collect();
+        let y_ct: Vec<_> = encrypt_bits(&y, enc_key, &mut rng).collect();
+        let x_dec: BitVec = decrypt_bits(&x_ct, &dec_key).collect();
+        let y_dec: BitVec = decrypt_bits(&y_ct, &dec_key).collect();
+        let expected = hamming_distance(&x_dec, &y_dec);
+
+        assert_eq!(expected, hamming_distance(&x, &y));
+        for window in [1, 7, 64, x.len(), x.len() + 1] {
+            assert_eq!(
+                hamming_distance_windowed(&x_ct, &y_ct, &dec_key, window),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_best_match_two_stage() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let probe = BitVec::<u8, Lsb0>::from_slice(&rng.gen::<[u8; 32]>());
+        let mut gallery: Vec<_> = (0..8)
+            .map(|_| BitVec::<u8, Lsb0>::from_slice(&rng.gen::<[u8; 32]>()))
+            .collect();
+        let mut close = probe.clone();
+        close[..10].iter_mut().for_each(|mut bit| *bit = !*bit);
+        gallery.insert(5, close);
+        let positions: Vec<_> = (0..probe.len()).step_by(4).collect();
+
+        assert_eq!(hamming_subset(&probe, &gallery[5], &positions), 3);
+        assert_eq!(best_match(&probe, &gallery), Some((5, 10)));
+        assert_eq!(
+            best_match_two_stage(&probe, &gallery, &positions, 16),
+            best_match(&probe, &gallery)
+        );
+        assert_eq!(best_match_two_stage(&probe, &gallery, &positions, 0), None);
+        assert_eq!(best_match(&probe, &[]), None);
+    }
+
+    #[test]
+    fn test_hamming_distance_encoded() {
+        let x = BitVec::<u8, Lsb0>::from_slice(&StdRng::seed_from_u64(7).gen::<[u8; 16]>());
+        let mut y = x.clone();
+        let flipped = !y[42];
+        y.set(42, flipped);
+        let x_enc: BitVec = encode_bits(&x).collect();
+        let y_enc: BitVec = encode_bits(&y).collect();
+
+        assert_eq!(hamming_distance(&x_enc, &y_enc), 2);
+        assert_eq!(hamming_distance_encoded(&x_enc, &y_enc), Ok(1));
+        assert_eq!(hamming_distance_encoded(&x_enc, &x_enc), Ok(0));
+
+        let mut corrupt = y_enc.clone();
+        let second = corrupt[2 * 5 + 1];
+        corrupt.set(2 * 5, second);
+        assert_eq!(
+            hamming_distance_encoded(&x_enc, &corrupt),
+            Err(DecodeError::InvalidPair(5))
+        );
+        assert_eq!(
+            hamming_distance_encoded(&x_enc[1..], &y_enc[1..]),
+            Err(DecodeError::OddLength(x_enc.len() - 1))
+        );
+    }
+
+    #[test]
+    fn test_add_codes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let dec_key = DecryptionKey::new(&mut rng);
+        let enc_key = dec_key.encryption_key();
+        let a: Vec<_> = encrypt_bits(bits![u8, Lsb0; 1, 1, 0, 0], enc_key, &mut rng).collect();
+        let b: Vec<_> = encrypt_bits(bits![u8, Lsb0; 1, 0, 1, 0], enc_key, &mut rng).collect();
+
+        let sum = add_codes(&a, &b).unwrap();
+
+        let expected = [2u32, 1, 1, 0].map(|m| &Scalar::from(m) * &GENERATOR_TABLE);
+        assert!(Iterator::eq(
+            sum.iter().map(|ct| dec_key.decrypt(*ct)),
+            expected
+        ));
+        assert_eq!(
+            add_codes(&a, &b[1..]),
+            Err(LengthMismatch { left: 4, right: 3 })
+        );
+    }
+
+    #[test]
+    fn test_gen_code_pair() {
+        let (x, y) = gen_code_pair(100, 5, &mut StdRng::seed_from_u64(7));
+
+        assert_eq!(x.len(), 200);
+        assert_eq!(hamming_distance_encoded(&x, &y), Ok(5));
+        assert_eq!((x, y), gen_code_pair(100, 5, &mut StdRng::seed_from_u64(7)));
+    }
+}
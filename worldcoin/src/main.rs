@@ -1,10 +1,9 @@
 //! Playground to test with the [`remix`] crate.
 
-use std::iter::once;
-
 use bitvec::prelude::*;
-use rand::{CryptoRng, Rng};
-use rust_elgamal::{Ciphertext, DecryptionKey, EncryptionKey, Scalar, GENERATOR_TABLE};
+use rand::Rng;
+use rust_elgamal::DecryptionKey;
+use worldcoin::{decode_bits, decrypt_bits, encode_bits, encrypt_codes};
 
 const N_SIZE: usize = 12800 / 8;
 
@@ -24,9 +23,8 @@ fn main() {
     let dec_key = DecryptionKey::new(&mut rng);
     let enc_key = dec_key.encryption_key();
 
-    let mut enc_new_user: Vec<_> = encrypt_bits(&new_user[..], enc_key, &mut rng).collect();
-    let mut enc_archived_user: Vec<_> =
-        encrypt_bits(&archived_user[..], enc_key, &mut rng).collect();
+    let (mut enc_new_user, mut enc_archived_user) =
+        encrypt_codes(&new_user, &archived_user, enc_key, &mut rng).unwrap();
 
     // Shuffle + Rerandomize
     let start = std::time::Instant::now();
@@ -43,48 +41,6 @@ fn main() {
     // Assert result
     assert_eq!(new_user.count_ones(), dec_new_user.count_ones());
     assert_eq!(archived_user.count_ones(), dec_archived_user.count_ones());
-}
-
-fn encode_bits<T: BitStore, O: BitOrder>(bits: &BitSlice<T, O>) -> impl Iterator<Item = bool> + '_ {
-    bits.iter().flat_map(|bit| {
-        let encoding = match *bit {
-            false /*0*/ => (false, true) /*01*/,
-            true  /*1*/ => (true, false) /*10*/,
-        };
-        once(encoding.0).chain(once(encoding.1))
-    })
-}
-
-fn encrypt_bits<'a, T: BitStore, O: BitOrder>(
-    bits: &'a BitSlice<T, O>,
-    ek: &'a EncryptionKey,
-    rng: &'a mut (impl Rng + CryptoRng + 'static),
-) -> impl Iterator<Item = Ciphertext> + 'a {
-    bits.iter()
-        .map(|bit| ek.encrypt(&Scalar::from(*bit as u32) * &GENERATOR_TABLE, rng))
-}
-
-fn decrypt_bits<'a>(
-    ct: &'a [Ciphertext],
-    pk: &'a DecryptionKey,
-) -> impl Iterator<Item = bool> + 'a {
-    ct.iter().map(|ct| {
-        let point = pk.decrypt(*ct);
-        point != (&Scalar::from(0u32) * &GENERATOR_TABLE)
-    })
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_encode_bits() {
-        let bits = BitVec::<u8, Msb0>::from_slice(&[0b11100100]);
-        let expected = BitVec::<u8, Msb0>::from_slice(&[0b10101001, 0b01100101]);
-
-        let enc_bits: BitVec<u8, Lsb0> = encode_bits(&bits[..]).collect();
-
-        assert_eq!(enc_bits, expected);
-    }
+    decode_bits(&dec_new_user).expect("remix keeps every pair a valid encoding");
+    decode_bits(&dec_archived_user).expect("remix keeps every pair a valid encoding");
 }